@@ -2,9 +2,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(feature = "std")]
-pub use std::os::raw as ctypes;
+pub mod ctypes {
+    pub use std::os::raw::*;
+
+    // `std::os::raw` doesn't provide these, so fill them in ourselves,
+    // matching the aliases used by the `no_std` paths below.
+    pub type c_size_t = libc::size_t;
+    pub type c_ssize_t = libc::ssize_t;
+    pub type c_ptrdiff_t = isize;
+}
 
-#[cfg(all(not(feature = "std"), feature = "no_std"))]
+#[cfg(all(not(feature = "std"), feature = "no_std", not(feature = "core_ffi_c")))]
 pub mod ctypes {
     // The signedness of `char` is platform-specific, however a consequence
     // of it being platform-specific is that any code which depends on the
@@ -38,9 +46,31 @@ pub mod ctypes {
     pub type c_float = f32;
     pub type c_double = f64;
 
+    // `size_t`/`ssize_t`/`ptrdiff_t`-equivalents, used to express syscall
+    // signatures that return or accept a byte count or pointer difference.
+    pub type c_size_t = usize;
+    pub type c_ssize_t = isize;
+    pub type c_ptrdiff_t = isize;
+
     pub use core::ffi::c_void;
 }
 
+// As of recent Rust versions, `core::ffi` provides the C FFI types directly,
+// so on those versions we can avoid hand-rolling them ourselves.
+#[cfg(all(not(feature = "std"), feature = "no_std", feature = "core_ffi_c"))]
+pub mod ctypes {
+    pub use core::ffi::{
+        c_char, c_double, c_float, c_int, c_long, c_longlong, c_schar, c_short, c_uchar, c_uint,
+        c_ulong, c_ulonglong, c_ushort, c_void,
+    };
+
+    // `core::ffi` doesn't (yet) provide these, so fill them in ourselves,
+    // matching the aliases used by the hand-rolled path above.
+    pub type c_size_t = usize;
+    pub type c_ssize_t = isize;
+    pub type c_ptrdiff_t = isize;
+}
+
 // Confirm that our type definitions above match the actual type definitions.
 #[cfg(test)]
 mod assertions {
@@ -58,6 +88,8 @@ mod assertions {
     static_assertions::assert_type_eq_all!(ctypes::c_ulonglong, libc::c_ulonglong);
     static_assertions::assert_type_eq_all!(ctypes::c_float, libc::c_float);
     static_assertions::assert_type_eq_all!(ctypes::c_double, libc::c_double);
+    static_assertions::assert_type_eq_all!(ctypes::c_size_t, libc::size_t);
+    static_assertions::assert_type_eq_all!(ctypes::c_ssize_t, libc::ssize_t);
 }
 
 // We don't enable `derive_eq` in bindgen because adding `PartialEq`/`Eq` to
@@ -80,7 +112,7 @@ impl Eq for general::__kernel_timespec {}
 
 #[cfg(feature = "general")]
 pub mod cmsg_macros {
-    use crate::ctypes::{c_long, c_uchar, c_uint};
+    use crate::ctypes::{c_int, c_long, c_uchar, c_uint};
     use crate::general::{cmsghdr, msghdr};
     use core::mem::size_of;
     use core::ptr;
@@ -133,6 +165,142 @@ pub mod cmsg_macros {
 
         next_cmsg
     }
+
+    /// A safe iterator over the control messages in a `msghdr`, built on
+    /// top of the raw `CMSG_FIRSTHDR`/`CMSG_NXTHDR`/`CMSG_DATA` pointer
+    /// arithmetic above.
+    ///
+    /// Each item is `(cmsg_level, cmsg_type, data)`, where `data` is the
+    /// control message's payload. Malformed or truncated headers (as
+    /// detected by `CMSG_FIRSTHDR`/`CMSG_NXTHDR`'s existing bounds checks,
+    /// a `cmsg_len` smaller than a `cmsghdr`, or a `cmsg_len` that runs past
+    /// the end of the control buffer) end the iteration rather than reading
+    /// out of bounds.
+    pub struct Messages<'a> {
+        mhdr: *const msghdr,
+        next: *const cmsghdr,
+        _phantom: core::marker::PhantomData<&'a msghdr>,
+    }
+
+    impl<'a> Messages<'a> {
+        /// Create an iterator over the control messages in `mhdr`.
+        pub fn new(mhdr: &'a msghdr) -> Self {
+            Self {
+                mhdr,
+                // Safety: `mhdr` is a valid `&msghdr` for the lifetime of `self`.
+                next: unsafe { CMSG_FIRSTHDR(mhdr) },
+                _phantom: core::marker::PhantomData,
+            }
+        }
+    }
+
+    impl<'a> Iterator for Messages<'a> {
+        type Item = (c_int, c_int, &'a [u8]);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.next.is_null() {
+                return None;
+            }
+
+            // Safety: `self.next` was produced by `CMSG_FIRSTHDR`/`CMSG_NXTHDR`,
+            // which only ever return null or a pointer to a `cmsghdr` that fits
+            // within `self.mhdr`'s control buffer.
+            let cmsg = unsafe { &*self.next };
+            let cmsg_len = cmsg.cmsg_len as usize;
+            let header_len = size_of::<cmsghdr>();
+
+            if cmsg_len < header_len {
+                self.next = ptr::null_mut();
+                return None;
+            }
+
+            // `CMSG_NXTHDR`'s bounds check only protects the *next* header
+            // lookup; it doesn't protect the payload of *this* header. Make
+            // sure a corrupted or truncated buffer whose `cmsg_len` claims
+            // more than the remaining control buffer doesn't turn into an
+            // out-of-bounds slice below.
+            let max = unsafe { (*self.mhdr).msg_control as usize + (*self.mhdr).msg_controllen as usize };
+            if self.next as usize + cmsg_len > max {
+                self.next = ptr::null_mut();
+                return None;
+            }
+
+            let data = unsafe { CMSG_DATA(self.next) } as *const u8;
+            let data_len = cmsg_len - header_len;
+            let data = unsafe { core::slice::from_raw_parts(data, data_len) };
+
+            let item = (cmsg.cmsg_level, cmsg.cmsg_type, data);
+            self.next = unsafe { CMSG_NXTHDR(self.mhdr, self.next) };
+            Some(item)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        extern crate alloc;
+
+        use super::*;
+        use alloc::{vec, vec::Vec};
+
+        // Writes a `cmsghdr` plus payload at `ptr` and returns the
+        // `CMSG_ALIGN`ed number of bytes written.
+        unsafe fn write_cmsg(ptr: *mut u8, level: c_int, ty: c_int, data: &[u8]) -> usize {
+            let header_len = size_of::<cmsghdr>();
+            let cmsg_len = (header_len + data.len()) as _;
+            let mut hdr: cmsghdr = core::mem::zeroed();
+            hdr.cmsg_len = cmsg_len;
+            hdr.cmsg_level = level;
+            hdr.cmsg_type = ty;
+            ptr::copy_nonoverlapping(&hdr as *const cmsghdr as *const u8, ptr, header_len);
+            ptr::copy_nonoverlapping(data.as_ptr(), ptr.add(header_len), data.len());
+            CMSG_ALIGN(cmsg_len as _) as usize
+        }
+
+        fn make_mhdr(buf: &mut [u64], controllen: usize) -> msghdr {
+            let mut mhdr: msghdr = unsafe { core::mem::zeroed() };
+            mhdr.msg_control = buf.as_mut_ptr() as *mut _;
+            mhdr.msg_controllen = controllen as _;
+            mhdr
+        }
+
+        #[test]
+        fn iterates_multiple_entries() {
+            let mut buf = [0u64; 32];
+            let ptr = buf.as_mut_ptr() as *mut u8;
+            let mut off = 0;
+            off += unsafe { write_cmsg(ptr.add(off), 1, 2, &[0xaa, 0xbb, 0xcc]) };
+            off += unsafe { write_cmsg(ptr.add(off), 3, 4, &[0x11, 0x22]) };
+
+            let mhdr = make_mhdr(&mut buf, off);
+            let items: Vec<_> = Messages::new(&mhdr)
+                .map(|(level, ty, data)| (level, ty, data.to_vec()))
+                .collect();
+
+            assert_eq!(
+                items,
+                vec![
+                    (1, 2, vec![0xaa, 0xbb, 0xcc]),
+                    (3, 4, vec![0x11, 0x22]),
+                ]
+            );
+        }
+
+        #[test]
+        fn stops_on_cmsg_len_past_the_control_buffer() {
+            let mut buf = [0u64; 4];
+            let ptr = buf.as_mut_ptr() as *mut u8;
+            let header_len = size_of::<cmsghdr>();
+
+            // Claim a `cmsg_len` far larger than the buffer actually holds.
+            unsafe { write_cmsg(ptr, 1, 1, &[]) };
+            let mut hdr: cmsghdr = unsafe { ptr::read(ptr as *const cmsghdr) };
+            hdr.cmsg_len = (header_len + 1000) as _;
+            unsafe { ptr::copy_nonoverlapping(&hdr as *const cmsghdr as *const u8, ptr, header_len) };
+
+            let mhdr = make_mhdr(&mut buf, header_len + 4);
+            assert_eq!(Messages::new(&mhdr).count(), 0);
+        }
+    }
 }
 
 #[cfg(feature = "general")]
@@ -189,6 +357,202 @@ pub mod signal_macros {
     }
 }
 
+#[cfg(feature = "ioctl")]
+pub mod ioctl_macros {
+    //! `const fn` encoders/decoders for ioctl request numbers, mirroring
+    //! `<asm-generic/ioctl.h>`. Most architectures pack the direction,
+    //! type, number and size fields the same way, but a handful (alpha,
+    //! mips, powerpc, sparc, and the `sw_64` target this crate is
+    //! generated for) use a different `_IOC_SIZEBITS`/`_IOC_DIRBITS` split
+    //! and direction encoding, so the constants below are picked per-arch.
+
+    use crate::ctypes::c_uint;
+
+    #[cfg(any(
+        target_arch = "sw_64",
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "sparc",
+        target_arch = "sparc64"
+    ))]
+    mod consts {
+        use crate::ctypes::c_uint;
+
+        pub(super) const _IOC_NRBITS: c_uint = 8;
+        pub(super) const _IOC_TYPEBITS: c_uint = 8;
+        pub(super) const _IOC_SIZEBITS: c_uint = 13;
+        pub(super) const _IOC_DIRBITS: c_uint = 3;
+
+        pub(super) const _IOC_NONE: c_uint = 1;
+        pub(super) const _IOC_READ: c_uint = 2;
+        pub(super) const _IOC_WRITE: c_uint = 4;
+    }
+
+    #[cfg(not(any(
+        target_arch = "sw_64",
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "sparc",
+        target_arch = "sparc64"
+    )))]
+    mod consts {
+        use crate::ctypes::c_uint;
+
+        pub(super) const _IOC_NRBITS: c_uint = 8;
+        pub(super) const _IOC_TYPEBITS: c_uint = 8;
+        pub(super) const _IOC_SIZEBITS: c_uint = 14;
+        pub(super) const _IOC_DIRBITS: c_uint = 2;
+
+        pub(super) const _IOC_NONE: c_uint = 0;
+        pub(super) const _IOC_WRITE: c_uint = 1;
+        pub(super) const _IOC_READ: c_uint = 2;
+    }
+
+    use consts::{
+        _IOC_DIRBITS, _IOC_NONE, _IOC_NRBITS, _IOC_READ, _IOC_SIZEBITS, _IOC_TYPEBITS, _IOC_WRITE,
+    };
+
+    const _IOC_NRSHIFT: c_uint = 0;
+    const _IOC_TYPESHIFT: c_uint = _IOC_NRSHIFT + _IOC_NRBITS;
+    const _IOC_SIZESHIFT: c_uint = _IOC_TYPESHIFT + _IOC_TYPEBITS;
+    const _IOC_DIRSHIFT: c_uint = _IOC_SIZESHIFT + _IOC_SIZEBITS;
+
+    const _IOC_NRMASK: c_uint = (1 << _IOC_NRBITS) - 1;
+    const _IOC_TYPEMASK: c_uint = (1 << _IOC_TYPEBITS) - 1;
+    const _IOC_SIZEMASK: c_uint = (1 << _IOC_SIZEBITS) - 1;
+    const _IOC_DIRMASK: c_uint = (1 << _IOC_DIRBITS) - 1;
+
+    /// Encode an ioctl request number from its direction, type, number and
+    /// size fields.
+    #[inline]
+    pub const fn _IOC(dir: c_uint, ty: c_uint, nr: c_uint, size: c_uint) -> c_uint {
+        (dir << _IOC_DIRSHIFT)
+            | (ty << _IOC_TYPESHIFT)
+            | (nr << _IOC_NRSHIFT)
+            | (size << _IOC_SIZESHIFT)
+    }
+
+    /// Encode an ioctl request number that transfers no data.
+    #[inline]
+    pub const fn _IO(ty: c_uint, nr: c_uint) -> c_uint {
+        _IOC(_IOC_NONE, ty, nr, 0)
+    }
+
+    /// Encode an ioctl request number that reads `size` bytes from the
+    /// kernel.
+    #[inline]
+    pub const fn _IOR(ty: c_uint, nr: c_uint, size: c_uint) -> c_uint {
+        _IOC(_IOC_READ, ty, nr, size)
+    }
+
+    /// Encode an ioctl request number that writes `size` bytes to the
+    /// kernel.
+    #[inline]
+    pub const fn _IOW(ty: c_uint, nr: c_uint, size: c_uint) -> c_uint {
+        _IOC(_IOC_WRITE, ty, nr, size)
+    }
+
+    /// Encode an ioctl request number that both reads and writes `size`
+    /// bytes.
+    #[inline]
+    pub const fn _IOWR(ty: c_uint, nr: c_uint, size: c_uint) -> c_uint {
+        _IOC(_IOC_READ | _IOC_WRITE, ty, nr, size)
+    }
+
+    /// Extract the direction field from an ioctl request number.
+    #[inline]
+    pub const fn _IOC_DIR(nr: c_uint) -> c_uint {
+        (nr >> _IOC_DIRSHIFT) & _IOC_DIRMASK
+    }
+
+    /// Extract the type field from an ioctl request number.
+    #[inline]
+    pub const fn _IOC_TYPE(nr: c_uint) -> c_uint {
+        (nr >> _IOC_TYPESHIFT) & _IOC_TYPEMASK
+    }
+
+    /// Extract the number field from an ioctl request number.
+    #[inline]
+    pub const fn _IOC_NR(nr: c_uint) -> c_uint {
+        (nr >> _IOC_NRSHIFT) & _IOC_NRMASK
+    }
+
+    /// Extract the size field from an ioctl request number.
+    #[inline]
+    pub const fn _IOC_SIZE(nr: c_uint) -> c_uint {
+        (nr >> _IOC_SIZESHIFT) & _IOC_SIZEMASK
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            let req = _IOWR(b'U' as c_uint, 42, 128);
+            assert_eq!(_IOC_DIR(req), _IOC_READ | _IOC_WRITE);
+            assert_eq!(_IOC_TYPE(req), b'U' as c_uint);
+            assert_eq!(_IOC_NR(req), 42);
+            assert_eq!(_IOC_SIZE(req), 128);
+        }
+
+        #[test]
+        fn io_has_no_direction_bits_set_besides_none() {
+            let req = _IO(b'X' as c_uint, 7);
+            assert_eq!(_IOC_DIR(req), _IOC_NONE);
+            assert_eq!(_IOC_NR(req), 7);
+            assert_eq!(_IOC_SIZE(req), 0);
+        }
+    }
+}
+
+#[cfg(feature = "general")]
+pub mod makedev_macros {
+    //! Device-number helpers matching the glibc/kernel 64-bit `dev_t`
+    //! encoding, for splitting and assembling the values used by `mknod`,
+    //! `stat`, and mount operations.
+
+    use crate::ctypes::c_uint;
+
+    /// Assemble a `dev_t` from its major and minor components.
+    #[inline]
+    pub const fn makedev(major: c_uint, minor: c_uint) -> u64 {
+        let major = major as u64;
+        let minor = minor as u64;
+        (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12) | ((major & !0xfff) << 32)
+    }
+
+    /// Extract the major component from a `dev_t`.
+    #[inline]
+    pub const fn major(dev: u64) -> c_uint {
+        (((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff)) as c_uint
+    }
+
+    /// Extract the minor component from a `dev_t`.
+    #[inline]
+    pub const fn minor(dev: u64) -> c_uint {
+        ((dev & 0xff) | ((dev >> 12) & !0xff)) as c_uint
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn roundtrip() {
+            for &(maj, min) in &[(0u32, 0u32), (8, 1), (0xabc, 0xff), (0x1_2345, 0xdead)] {
+                let dev = makedev(maj, min);
+                assert_eq!(major(dev), maj);
+                assert_eq!(minor(dev), min);
+            }
+        }
+    }
+}
+
 // The rest of this file is auto-generated!
 #[cfg(feature = "errno")]
 #[path = "sw_64/errno.rs"]